@@ -0,0 +1,99 @@
+// Copyright 2016 lazy-static.rs Developers
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Backend selected under `--features nightly,spin_no_std` -- true `no_std`,
+//! with no dependency on `std::sync::Once`.
+//!
+//! Identical in shape to `inline_lazy.rs`, but guarded by `spin::Once`
+//! instead of `std::sync::Once`.
+
+use core::cell::UnsafeCell;
+use core::mem::MaybeUninit;
+use core::ops::Deref;
+use spin::Once;
+
+/// A value that is lazily initialized on first access. See
+/// `lazy_static::Lazy` (this is re-exported as that type on this backend).
+pub struct Lazy<T, F = fn() -> T> {
+    cell: UnsafeCell<MaybeUninit<T>>,
+    once: Once,
+    init: UnsafeCell<Option<F>>,
+}
+
+impl<T, F> Lazy<T, F> {
+    /// Creates a new `Lazy` that will run `init` to produce its value on
+    /// first access.
+    pub const fn new(init: F) -> Self {
+        Lazy {
+            cell: UnsafeCell::new(MaybeUninit::uninit()),
+            once: Once::new(),
+            init: UnsafeCell::new(Some(init)),
+        }
+    }
+}
+
+impl<T, F> Lazy<T, F>
+    where F: FnOnce() -> T
+{
+    /// Returns a reference to the value, initializing it with `init` on the
+    /// first call. `spin::Once` does not poison on a panicking `init`, but
+    /// `init` is consumed the first time `get` is called regardless of
+    /// outcome, so a caller that catches the unwind and retries panics on
+    /// the missing initializer rather than running `init` again.
+    #[inline(always)]
+    pub fn get(&self) -> &T {
+        self.once.call_once(|| unsafe {
+            // `call_once` guarantees this closure runs at most once, so
+            // taking the `Option` out of the cell is safe even though
+            // several threads may be racing to reach this point.
+            let init = (*self.init.get()).take().unwrap();
+            (*self.cell.get()).write(init());
+        });
+
+        // `call_once` has returned, so the cell is guaranteed to have been
+        // written to exactly once; reading it back is sound.
+        unsafe {
+            &*(*self.cell.get()).as_ptr()
+        }
+    }
+}
+
+impl<T, F> Deref for Lazy<T, F>
+    where F: FnOnce() -> T
+{
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        self.get()
+    }
+}
+
+impl<T, F> Drop for Lazy<T, F> {
+    fn drop(&mut self) {
+        // Only a completed `once` ever wrote to `cell`; an uninitialized
+        // `Lazy` has nothing to drop.
+        if self.once.is_completed() {
+            unsafe {
+                core::ptr::drop_in_place((*self.cell.get()).as_mut_ptr());
+            }
+        }
+    }
+}
+
+// `F` may be an arbitrary closure capturing non-`Send` state and `get` can
+// run it on whichever thread first calls it, so `F` (and the `T` it
+// produces) must be `Send` for a shared `&Lazy` to be safe across threads,
+// mirroring `once_cell`/`std`.
+unsafe impl<T: Sync + Send, F: Send> Sync for Lazy<T, F> {}
+
+#[macro_export]
+#[doc(hidden)]
+macro_rules! __lazy_static_create {
+    ($NAME:ident, $T:ty) => {
+        static $NAME: $crate::lazy::Lazy<$T> = $crate::lazy::Lazy::new(__static_ref_initialize);
+    }
+}
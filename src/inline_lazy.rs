@@ -0,0 +1,127 @@
+// Copyright 2016 lazy-static.rs Developers
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+use core::cell::UnsafeCell;
+use core::mem::MaybeUninit;
+use core::ops::Deref;
+#[cfg(not(feature="spin_no_std"))]
+use std::sync::Once;
+#[cfg(feature="spin_no_std")]
+use spin::Once;
+
+/// A value that is lazily initialized on first access.
+///
+/// This is the type that backs every `lazy_static! { static ref NAME: TYPE
+/// = EXPR; }` declaration, but it is also usable on its own, without the
+/// macro -- in a plain `static` as below, or as a struct field or a local
+/// binding:
+///
+/// ```rust
+/// use lazy_static::Lazy;
+///
+/// static GREETING: Lazy<String> = Lazy::new(|| "hello".to_owned() + " world");
+///
+/// fn main() {
+///     assert_eq!(&*GREETING, "hello world");
+/// }
+/// ```
+///
+/// `EXPR` is only evaluated once, the first time the value is dereferenced;
+/// every later access returns a reference to the same, cached value. The
+/// value lives inline in the `Lazy` itself, so no heap allocation occurs.
+///
+/// `Lazy` itself places no bounds on `T` or `F`, so it can be used as a
+/// purely single-threaded lazy cell (e.g. `Lazy<Cell<u32>>` in a local
+/// binding). It is only `Sync` -- and therefore only usable in a `static`
+/// -- when `T` and `F` are themselves `Send + Sync`/`Send`.
+///
+/// If `T` was initialized, its value is dropped when the `Lazy` is, just as
+/// it would be for `once_cell::sync::Lazy` or `std::sync::LazyLock`.
+pub struct Lazy<T, F = fn() -> T> {
+    cell: UnsafeCell<MaybeUninit<T>>,
+    once: Once,
+    init: UnsafeCell<Option<F>>,
+}
+
+impl<T, F> Lazy<T, F> {
+    /// Creates a new `Lazy` that will run `init` to produce its value on
+    /// first access.
+    pub const fn new(init: F) -> Self {
+        Lazy {
+            cell: UnsafeCell::new(MaybeUninit::uninit()),
+            once: Once::new(),
+            init: UnsafeCell::new(Some(init)),
+        }
+    }
+}
+
+impl<T, F> Lazy<T, F>
+    where F: FnOnce() -> T
+{
+    /// Returns a reference to the value, initializing it with `init` on the
+    /// first call.
+    ///
+    /// `init` is consumed the first time this is called, whether or not it
+    /// panics. On the std backend that is moot: a panic poisons the `Lazy`,
+    /// just as it would poison a `std::sync::Once`, so every later access
+    /// panics too and `init` is never reached again. On the `spin_no_std`
+    /// backend `spin::Once` does *not* poison on panic, but `init` has
+    /// already been taken, so a caller that catches the unwind and retries
+    /// panics on the missing initializer rather than running `init` again.
+    #[inline(always)]
+    pub fn get(&self) -> &T {
+        self.once.call_once(|| unsafe {
+            // `call_once` guarantees this closure runs at most once, so
+            // taking the `Option` out of the cell is safe even though
+            // several threads may be racing to reach this point.
+            let init = (*self.init.get()).take().unwrap();
+            (*self.cell.get()).write(init());
+        });
+
+        // `call_once` has returned, so the cell is guaranteed to have been
+        // written to exactly once; reading it back is sound.
+        unsafe {
+            &*(*self.cell.get()).as_ptr()
+        }
+    }
+}
+
+impl<T, F> Deref for Lazy<T, F>
+    where F: FnOnce() -> T
+{
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        self.get()
+    }
+}
+
+impl<T, F> Drop for Lazy<T, F> {
+    fn drop(&mut self) {
+        // Only a completed `once` ever wrote to `cell`; an uninitialized
+        // `Lazy` has nothing to drop.
+        if self.once.is_completed() {
+            unsafe {
+                core::ptr::drop_in_place((*self.cell.get()).as_mut_ptr());
+            }
+        }
+    }
+}
+
+// `F` may be an arbitrary closure capturing non-`Send` state (e.g. an
+// `Rc`), and `get` can run it on whichever thread first calls `get`/deref;
+// requiring `F: Send` (and `T: Send` for the value it produces, mirroring
+// `once_cell`/`std`) is what makes that safe to do from a shared `&Lazy`.
+unsafe impl<T: Sync + Send, F: Send> Sync for Lazy<T, F> {}
+
+#[macro_export]
+#[doc(hidden)]
+macro_rules! __lazy_static_create {
+    ($NAME:ident, $T:ty) => {
+        static $NAME: $crate::lazy::Lazy<$T> = $crate::lazy::Lazy::new(__static_ref_initialize);
+    }
+}
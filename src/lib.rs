@@ -86,7 +86,7 @@ fn main() {
 
 # Implementation details
 
-The `Deref` implementation uses a hidden static variable that is guarded by a atomic check on each access. On stable Rust, the macro may need to allocate each static on the heap.
+The `Deref` implementation uses a hidden static variable that is guarded by a `Once` check on each access. The value is stored inline in the generated static, so no heap allocation occurs.
 
 */
 
@@ -95,20 +95,41 @@ The `Deref` implementation uses a hidden static variable that is guarded by a at
 #![doc(html_root_url = "https://docs.rs/lazy_static/0.2.6")]
 #![no_std]
 
-#[cfg(not(feature="nightly"))]
+#[cfg(not(feature="spin_no_std"))]
+extern crate std;
+
+#[cfg(feature="spin_no_std")]
+extern crate spin;
+
+#[cfg(loom)]
+extern crate loom;
+
+#[cfg(all(not(feature="nightly"), not(loom)))]
+#[path="inline_lazy.rs"]
 #[doc(hidden)]
 pub mod lazy;
 
-#[cfg(all(feature="nightly", not(feature="spin_no_std")))]
+#[cfg(all(feature="nightly", not(feature="spin_no_std"), not(loom)))]
 #[path="nightly_lazy.rs"]
 #[doc(hidden)]
 pub mod lazy;
 
-#[cfg(all(feature="nightly", feature="spin_no_std"))]
+#[cfg(all(feature="nightly", feature="spin_no_std", not(loom)))]
 #[path="core_lazy.rs"]
 #[doc(hidden)]
 pub mod lazy;
 
+#[cfg(loom)]
+#[path="loom_lazy.rs"]
+#[doc(hidden)]
+pub mod lazy;
+
+#[cfg(not(feature="spin_no_std"))]
+#[doc(hidden)]
+pub mod try_lazy;
+
+pub use lazy::Lazy;
+
 #[doc(hidden)]
 pub use core::ops::Deref as __Deref;
 
@@ -135,7 +156,7 @@ macro_rules! __lazy_static_internal {
                     #[inline(always)]
                     unsafe fn __stability() -> &'static $T {
                         __lazy_static_create!(LAZY, $T);
-                        LAZY.get(__static_ref_initialize)
+                        LAZY.get()
                     }
                     __stability()
                 }
@@ -181,6 +202,64 @@ macro_rules! lazy_static {
     () => ()
 }
 
+#[cfg(not(feature="spin_no_std"))]
+#[macro_export]
+#[doc(hidden)]
+macro_rules! __lazy_static_try_internal {
+    ($(#[$attr:meta])* static ref $N:ident : Result<$OkT:ty, $ErrT:ty> = $e:expr; $($t:tt)*) => {
+        __lazy_static_try_internal!(@PRIV, $(#[$attr])* static ref $N : Result<$OkT, $ErrT> = $e; $($t)*);
+    };
+    ($(#[$attr:meta])* pub static ref $N:ident : Result<$OkT:ty, $ErrT:ty> = $e:expr; $($t:tt)*) => {
+        __lazy_static_try_internal!(@PUB, $(#[$attr])* static ref $N : Result<$OkT, $ErrT> = $e; $($t)*);
+    };
+    (@$VIS:ident, $(#[$attr:meta])* static ref $N:ident : Result<$OkT:ty, $ErrT:ty> = $e:expr; $($t:tt)*) => {
+        __lazy_static_internal!(@MAKE TY, $VIS, $(#[$attr])*, $N);
+        impl $N {
+            /// Runs the initializer if no attempt has succeeded yet, and
+            /// returns a reference to the cached value, or to the error
+            /// from the most recent failed attempt.
+            #[allow(unsafe_code)]
+            pub fn get(&self) -> ::core::result::Result<&$OkT, &$ErrT> {
+                unsafe {
+                    #[inline(always)]
+                    fn __static_ref_initialize() -> ::core::result::Result<$OkT, $ErrT> { $e }
+
+                    #[inline(always)]
+                    unsafe fn __stability() -> &'static $crate::try_lazy::Lazy<$OkT, $ErrT> {
+                        __lazy_static_try_create!(LAZY, $OkT, $ErrT);
+                        &LAZY
+                    }
+                    __stability().get()
+                }
+            }
+        }
+        impl $crate::TryLazyStatic for $N {
+            type Ok = $OkT;
+            type Err = $ErrT;
+            fn try_initialize(&self) -> ::core::result::Result<(), &Self::Err> {
+                self.get().map(|_| ())
+            }
+        }
+        __lazy_static_try_internal!($($t)*);
+    };
+    () => ()
+}
+
+/// Like `lazy_static!`, but for a `static ref NAME: Result<T, E> = EXPR;`
+/// whose `EXPR` may fail. See [`TryLazyStatic`] for how to observe a
+/// failure and retry.
+#[cfg(not(feature="spin_no_std"))]
+#[macro_export]
+macro_rules! lazy_static_try {
+    ($(#[$attr:meta])* static ref $N:ident : Result<$OkT:ty, $ErrT:ty> = $e:expr; $($t:tt)*) => {
+        __lazy_static_try_internal!(@PRIV, $(#[$attr])* static ref $N : Result<$OkT, $ErrT> = $e; $($t)*);
+    };
+    ($(#[$attr:meta])* pub static ref $N:ident : Result<$OkT:ty, $ErrT:ty> = $e:expr; $($t:tt)*) => {
+        __lazy_static_try_internal!(@PUB, $(#[$attr])* static ref $N : Result<$OkT, $ErrT> = $e; $($t)*);
+    };
+    () => ()
+}
+
 /// Support trait for enabling a few common operation on lazy static values.
 ///
 /// This is implemented by each defined lazy static, and
@@ -216,3 +295,33 @@ pub trait LazyStatic {
 pub fn initialize<T: LazyStatic>(lazy: &T) {
     LazyStatic::initialize(lazy);
 }
+
+/// Support trait for lazy statics declared with `lazy_static_try!`.
+///
+/// Unlike [`LazyStatic`], a failed attempt does not poison the static: it
+/// is reported back to the caller, and the next call to `try_initialize`
+/// runs the initializer again.
+#[cfg(not(feature="spin_no_std"))]
+pub trait TryLazyStatic {
+    /// The value produced by a successful initialization.
+    type Ok;
+    /// The error produced by a failed initialization.
+    type Err;
+
+    /// Runs the initializer if it has not yet succeeded.
+    ///
+    /// Returns `Ok(())` once a value has been cached (whether by this call
+    /// or an earlier one), or `Err` with a reference to the error from the
+    /// most recent failed attempt otherwise.
+    fn try_initialize(&self) -> Result<(), &Self::Err>;
+}
+
+/// Takes a shared reference to a `lazy_static_try!` static and initializes
+/// it if it has not already succeeded.
+///
+/// Mirrors [`initialize`], but for fallible statics: see
+/// [`TryLazyStatic::try_initialize`].
+#[cfg(not(feature="spin_no_std"))]
+pub fn try_initialize<T: TryLazyStatic>(lazy: &T) -> Result<(), &T::Err> {
+    TryLazyStatic::try_initialize(lazy)
+}
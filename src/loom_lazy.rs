@@ -0,0 +1,83 @@
+// Copyright 2016 lazy-static.rs Developers
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! `loom`-aware backend, used when model-checking with `#[cfg(loom)]`.
+//!
+//! The real backends guard first-init with `std::sync::Once` or
+//! `spin::Once`, both of which are opaque to `loom`'s scheduler: the
+//! happens-before edge established by winning the race to initialize is
+//! never explored as a distinct interleaving. Swapping in `loom::sync::Once`
+//! makes that edge visible, but `loom::sync::Once` and `loom::sync::atomic`
+//! types are not `const fn`-constructible (they register with `loom`'s
+//! runtime at creation time) and, unlike real sync primitives, must not be
+//! reused from one `loom` execution to the next -- `loom::model` re-runs
+//! its closure body many times and expects the objects under test to be
+//! built fresh on every one of those runs, not shared via a process-level
+//! `static`.
+//!
+//! So `Lazy` itself stores only what *is* const-constructible: the
+//! initializer function, plus a `PhantomData` to remember `T`. The actual
+//! `Once` and value slot are created inside `get`, via `loom::lazy_static!`
+//! -- which, unlike a plain `static`, is torn down and rebuilt at the start
+//! of every `loom` execution, which is what makes the happens-before edge
+//! visible run after run instead of only on the very first one.
+
+use core::marker::PhantomData;
+use core::sync::atomic::Ordering;
+use loom::sync::atomic::AtomicPtr;
+use std::boxed::Box;
+
+#[doc(hidden)]
+pub struct Lazy<T: Sync> {
+    // Stored as a plain fn pointer (rather than threading a closure through
+    // `get`) so `new` can remain a `const fn`; that in turn forces this
+    // field to be `pub`, since a `const` item built from `Lazy::new` in
+    // user code can only be driven by code that can name the field.
+    #[doc(hidden)]
+    pub init: fn() -> T,
+    #[doc(hidden)]
+    pub _marker: PhantomData<T>,
+}
+
+impl<T: Sync> Lazy<T> {
+    #[doc(hidden)]
+    pub const fn new(init: fn() -> T) -> Self {
+        Lazy { init: init, _marker: PhantomData }
+    }
+
+    #[inline(always)]
+    #[doc(hidden)]
+    pub fn get(&self) -> &T {
+        // Type-erased (`AtomicPtr<()>`, not `AtomicPtr<T>`) so this slot
+        // stays a concrete type regardless of `T`, which is what lets it
+        // live inside a generic function body at all.
+        loom::lazy_static! {
+            static ref GUARD: (loom::sync::Once, AtomicPtr<()>) =
+                (loom::sync::Once::new(), AtomicPtr::new(core::ptr::null_mut()));
+        }
+        let (once, slot) = &*GUARD;
+        let init = self.init;
+        once.call_once(|| {
+            let value = init();
+            // The leaked allocation only needs to outlive the current
+            // execution, which `Box::leak` guarantees unconditionally.
+            let ptr = Box::leak(Box::new(value)) as *mut T as *mut ();
+            slot.store(ptr, Ordering::Release);
+        });
+        unsafe { &*(slot.load(Ordering::Acquire) as *mut T) }
+    }
+}
+
+unsafe impl<T: Sync> Sync for Lazy<T> {}
+
+#[macro_export]
+#[doc(hidden)]
+macro_rules! __lazy_static_create {
+    ($NAME:ident, $T:ty) => {
+        static $NAME: $crate::lazy::Lazy<$T> = $crate::lazy::Lazy::new(__static_ref_initialize);
+    }
+}
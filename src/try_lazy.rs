@@ -0,0 +1,204 @@
+// Copyright 2016 lazy-static.rs Developers
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Backend for `lazy_static_try!`, used by fallible initializers.
+//!
+//! `Lazy` caches a successful value forever behind a plain `Once`-style
+//! guard. A fallible initializer needs different semantics: a failed
+//! attempt must be observable (so the caller can see *why* it failed) but
+//! must **not** poison the static the way a panicking `Once` would, since
+//! the whole point of `Result<T, E>` over panicking is that the caller gets
+//! to decide whether to try again. So this backend tracks three states
+//! instead of the usual binary initialized/not-initialized guard: never
+//! run, running, and successfully cached; a failed run falls back to
+//! "never run" so the next access retries `init` from scratch.
+//!
+//! Because a later retry may run concurrently with another thread still
+//! holding the `&E` from a previous failed attempt, a failure is not stored
+//! anywhere that a retry could overwrite: it is boxed and leaked on the
+//! spot, so the `&E` handed back to the failing caller remains valid for
+//! the life of the program. Every failed attempt leaks its error this way;
+//! a successful attempt leaks nothing.
+
+use core::cell::UnsafeCell;
+use core::marker::PhantomData;
+use core::mem::MaybeUninit;
+use core::sync::atomic::{AtomicU8, Ordering};
+use std::boxed::Box;
+
+const UNINIT: u8 = 0;
+const RUNNING: u8 = 1;
+const READY: u8 = 2;
+
+#[doc(hidden)]
+pub struct Lazy<T, E, F = fn() -> Result<T, E>> {
+    cell: UnsafeCell<MaybeUninit<T>>,
+    state: AtomicU8,
+    init: F,
+    _err: PhantomData<E>,
+}
+
+impl<T, E, F> Lazy<T, E, F> {
+    #[doc(hidden)]
+    pub const fn new(init: F) -> Self {
+        Lazy {
+            cell: UnsafeCell::new(MaybeUninit::uninit()),
+            state: AtomicU8::new(UNINIT),
+            init: init,
+            _err: PhantomData,
+        }
+    }
+}
+
+impl<T, E, F> Lazy<T, E, F>
+    where F: Fn() -> Result<T, E>
+{
+    /// Returns a reference to the cached value, running `init` if no
+    /// attempt has succeeded yet. If `init` returns `Err`, that error is
+    /// returned and the next call to `get` tries `init` again.
+    #[doc(hidden)]
+    pub fn get(&self) -> Result<&T, &E> {
+        loop {
+            match self.state.load(Ordering::Acquire) {
+                READY => return Ok(unsafe { &*(*self.cell.get()).as_ptr() }),
+                RUNNING => core::hint::spin_loop(),
+                _ => {
+                    if self.state.compare_exchange(
+                        UNINIT, RUNNING, Ordering::Acquire, Ordering::Acquire
+                    ).is_ok() {
+                        match (self.init)() {
+                            Ok(value) => unsafe {
+                                (*self.cell.get()).write(value);
+                                self.state.store(READY, Ordering::Release);
+                            },
+                            Err(e) => {
+                                // Fall back to "never run" so a later
+                                // caller retries, rather than caching this
+                                // failure the way `Once` would.
+                                self.state.store(UNINIT, Ordering::Release);
+                                return Err(Box::leak(Box::new(e)));
+                            }
+                        }
+                    }
+                    // Lost the race (or just reset it ourselves above);
+                    // loop around to observe the winner's result or retry.
+                }
+            }
+        }
+    }
+}
+
+impl<T, E, F> Drop for Lazy<T, E, F> {
+    fn drop(&mut self) {
+        // Only a `READY` state ever wrote to `cell`; anything else (never
+        // run, or reset back to `UNINIT` after a failed attempt) has
+        // nothing to drop.
+        if *self.state.get_mut() == READY {
+            unsafe {
+                core::ptr::drop_in_place((*self.cell.get()).as_mut_ptr());
+            }
+        }
+    }
+}
+
+// `F` may be an arbitrary closure capturing non-`Send` state and `get` can
+// run it on whichever thread first calls it, so `F` (and the `T`/`E` it
+// produces) must be `Send` for a shared `&Lazy` to be safe across threads,
+// mirroring `once_cell`/`std`.
+unsafe impl<T: Sync + Send, E: Sync + Send, F: Send> Sync for Lazy<T, E, F> {}
+
+#[macro_export]
+#[doc(hidden)]
+macro_rules! __lazy_static_try_create {
+    ($NAME:ident, $OkT:ty, $ErrT:ty) => {
+        static $NAME: $crate::try_lazy::Lazy<$OkT, $ErrT> =
+            $crate::try_lazy::Lazy::new(__static_ref_initialize);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Lazy;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[test]
+    fn caches_first_success() {
+        static CALLS: AtomicUsize = AtomicUsize::new(0);
+        let lazy: Lazy<u32, i32, _> = Lazy::new(|| {
+            CALLS.fetch_add(1, Ordering::SeqCst);
+            Ok(42)
+        });
+
+        assert_eq!(lazy.get(), Ok(&42));
+        assert_eq!(lazy.get(), Ok(&42));
+        assert_eq!(CALLS.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn retries_after_error() {
+        static CALLS: AtomicUsize = AtomicUsize::new(0);
+        let lazy: Lazy<u32, i32, _> = Lazy::new(|| {
+            if CALLS.fetch_add(1, Ordering::SeqCst) == 0 {
+                Err(-1)
+            } else {
+                Ok(7)
+            }
+        });
+
+        assert_eq!(lazy.get(), Err(&-1));
+        assert_eq!(lazy.get(), Ok(&7));
+        assert_eq!(lazy.get(), Ok(&7));
+        assert_eq!(CALLS.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn concurrent_callers_see_a_single_successful_init() {
+        use std::sync::Arc;
+        use std::thread;
+        use std::vec::Vec;
+
+        static CALLS: AtomicUsize = AtomicUsize::new(0);
+        let lazy: Arc<Lazy<u32, i32, fn() -> Result<u32, i32>>> = Arc::new(Lazy::new(|| {
+            CALLS.fetch_add(1, Ordering::SeqCst);
+            Ok(99)
+        }));
+
+        let handles: Vec<_> = (0..8)
+            .map(|_| {
+                let lazy = lazy.clone();
+                thread::spawn(move || *lazy.get().unwrap())
+            })
+            .collect();
+
+        for handle in handles {
+            assert_eq!(handle.join().unwrap(), 99);
+        }
+        assert_eq!(CALLS.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn drops_a_cached_value_but_not_after_a_failed_attempt() {
+        static DROPS: AtomicUsize = AtomicUsize::new(0);
+
+        struct CountDrops;
+        impl Drop for CountDrops {
+            fn drop(&mut self) {
+                DROPS.fetch_add(1, Ordering::SeqCst);
+            }
+        }
+
+        let lazy: Lazy<CountDrops, i32, _> = Lazy::new(|| Err(-1));
+        assert!(lazy.get().is_err());
+        drop(lazy);
+        assert_eq!(DROPS.load(Ordering::SeqCst), 0);
+
+        let lazy: Lazy<CountDrops, i32, _> = Lazy::new(|| Ok(CountDrops));
+        assert!(lazy.get().is_ok());
+        drop(lazy);
+        assert_eq!(DROPS.load(Ordering::SeqCst), 1);
+    }
+}